@@ -0,0 +1,115 @@
+use clap::{Parser, Subcommand, ValueEnum};
+
+use crate::ndjson::ExportFormat;
+
+/// Process biosample records from MongoDB into a local analytics database
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+pub struct Cli {
+    /// MongoDB connection string
+    #[arg(long, default_value = "mongodb://localhost:27017", global = true)]
+    pub mongo_uri: String,
+
+    /// MongoDB database name
+    #[arg(long, default_value = "biosamples", global = true)]
+    pub mongo_db: String,
+
+    /// MongoDB collection name
+    #[arg(long, default_value = "biosamples", global = true)]
+    pub mongo_collection: String,
+
+    /// Enable verbose logging
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Extract biosamples from MongoDB into the `--output-backend` database (DuckDB by default)
+    Export(ExportArgs),
+    /// Check that every biosample parses cleanly, without writing anything
+    Validate(ScanArgs),
+    /// Stream the collection and report summary statistics
+    Stats(ScanArgs),
+    /// Resume a previously interrupted export from its last committed watermark
+    Resume(ExportArgs),
+}
+
+#[derive(Parser, Debug)]
+pub struct ExportArgs {
+    /// Output DuckDB database file
+    #[arg(long, default_value = "biosample_attributes.db")]
+    pub output_db: String,
+
+    /// Number of biosamples to process (0 for all)
+    #[arg(long, default_value = "10")]
+    pub limit: i64,
+
+    /// Total number of biosamples in MongoDB, used for the progress bar
+    #[arg(long, default_value = "45000000")]
+    pub total_biosamples: u64,
+
+    /// Number of biosamples to buffer before flushing a transaction to DuckDB
+    #[arg(long, default_value = "10000")]
+    pub batch_size: u64,
+
+    /// Tee raw biosample documents to a compressed NDJSON file as they stream by
+    #[arg(long, value_enum, requires = "export_file")]
+    pub export_format: Option<ExportFormat>,
+
+    /// Path for the teed NDJSON file; compressed per `--export-format`, or by the
+    /// path's extension when that is unset
+    #[arg(long)]
+    pub export_file: Option<String>,
+
+    /// Replay a previously exported NDJSON file instead of connecting to MongoDB
+    #[arg(long)]
+    pub input_file: Option<String>,
+
+    /// Resume from the last committed `_id` watermark instead of starting over
+    #[arg(long)]
+    pub resume: bool,
+
+    /// Persist the `extra` flatten fields instead of discarding them
+    #[arg(long, value_enum)]
+    pub extra_mode: Option<ExtraMode>,
+
+    /// Database backend to write to
+    #[arg(long, value_enum, default_value = "duckdb")]
+    pub output_backend: OutputBackend,
+
+    /// Connection URL for `--output-backend postgres` or `sqlite` (ignored for duckdb)
+    #[arg(long)]
+    pub output_url: Option<String>,
+}
+
+/// Which database the extracted rows are written to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputBackend {
+    Duckdb,
+    Postgres,
+    Sqlite,
+}
+
+/// How to persist the fields captured by each model's `#[serde(flatten)] extra`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ExtraMode {
+    /// Store the whole `extra` map as a single JSON column
+    Json,
+    /// Evolve the table schema, adding one VARCHAR column per new key seen
+    Columns,
+}
+
+#[derive(Parser, Debug)]
+pub struct ScanArgs {
+    /// Number of biosamples to process (0 for all)
+    #[arg(long, default_value = "10")]
+    pub limit: i64,
+
+    /// Total number of biosamples in MongoDB, used for the progress bar
+    #[arg(long, default_value = "45000000")]
+    pub total_biosamples: u64,
+}
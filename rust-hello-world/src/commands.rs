@@ -0,0 +1,478 @@
+use duckdb::Result;
+use futures::stream::TryStreamExt;
+use indicatif::{ProgressBar, ProgressStyle};
+use mongodb::bson::doc;
+
+use crate::cli::{ExportArgs, ExtraMode, OutputBackend, ScanArgs};
+use crate::models::{AttributeData, Biosample, PackageData};
+use crate::mongo;
+use crate::ndjson::{ExportFormat, NdjsonReader, NdjsonWriter};
+use crate::sink::{split_extra, AttrRow, DuckDbSink, PkgRow, PostgresSink, Sink, SqliteSink};
+
+fn progress_bar(total: u64) -> ProgressBar {
+    let pb = ProgressBar::new(total);
+    pb.set_style(ProgressStyle::default_bar()
+        .template("{spinner:.green} [{elapsed_precise}] [{wide_bar:.cyan/blue}] {pos}/{len} ({per_sec}, {eta})")
+        .unwrap()
+        .progress_chars("#>-"));
+    pb
+}
+
+/// Where the export subcommand reads biosamples from: a live Mongo cursor, or
+/// a previously exported NDJSON file being replayed via `--input-file`.
+enum BiosampleSource {
+    Mongo(Box<mongodb::Cursor<Biosample>>),
+    File(NdjsonReader),
+}
+
+impl BiosampleSource {
+    async fn next(&mut self) -> Result<Option<Biosample>, Box<dyn std::error::Error>> {
+        match self {
+            BiosampleSource::Mongo(cursor) => Ok(cursor.try_next().await?),
+            BiosampleSource::File(reader) => Ok(reader.next_biosample()?),
+        }
+    }
+}
+
+/// Parse a biosample's attribute/package documents into sink-ready rows,
+/// logging parse failures and extra fields in verbose mode.
+fn extract_rows(
+    biosample: &Biosample,
+    verbose: bool,
+    extra_mode: Option<ExtraMode>,
+) -> (Vec<AttrRow>, Vec<PkgRow>) {
+    let biosample_id = biosample.id.parse::<i64>().unwrap_or(-1);
+    let mut attr_rows = Vec::new();
+    let mut pkg_rows = Vec::new();
+
+    // Process Attributes
+    if let Some(attributes) = &biosample.attributes {
+        if let Some(attribute_obj) = attributes.as_object() {
+            if let Some(attribute_array) = attribute_obj.get("Attribute") {
+                if let Some(attrs) = attribute_array.as_array() {
+                    if verbose {
+                        println!("  Found {} attributes", attrs.len());
+                    }
+                    for attr_value in attrs {
+                        if let Ok(attr) = serde_json::from_value::<AttributeData>(attr_value.clone())
+                        {
+                            if !attr.extra.is_empty() && verbose {
+                                println!("  Extra attribute fields found: {:?}", attr.extra);
+                            }
+
+                            let (extra_json, extra_columns) = split_extra(&attr.extra, extra_mode);
+
+                            attr_rows.push(AttrRow {
+                                content: attr.content.unwrap_or_default(),
+                                attribute_name: attr.attribute_name.unwrap_or_default(),
+                                id: biosample_id,
+                                harmonized_name: attr.harmonized_name.unwrap_or_default(),
+                                display_name: attr.display_name.unwrap_or_default(),
+                                unit: attr.unit.unwrap_or_default(),
+                                extra_json,
+                                extra_columns,
+                            });
+                        } else if verbose {
+                            println!("  Failed to parse attribute: {:?}", attr_value);
+                        }
+                    }
+                } else if verbose {
+                    println!("  Attribute is not an array");
+                }
+            } else if verbose {
+                println!("  No Attribute field found");
+            }
+        } else if verbose {
+            println!("  Attributes is not an object");
+        }
+    } else if verbose {
+        println!("  No Attributes found");
+    }
+
+    // Process Package
+    if let Some(package) = &biosample.package {
+        match serde_json::from_value::<PackageData>(package.clone()) {
+            Ok(pkg) => {
+                if !pkg.extra.is_empty() && verbose {
+                    println!(
+                        "Extra package fields found for biosample {}: {:?}",
+                        biosample.id, pkg.extra
+                    );
+                }
+
+                let (extra_json, extra_columns) = split_extra(&pkg.extra, extra_mode);
+
+                pkg_rows.push(PkgRow {
+                    content: pkg.content.unwrap_or_default(),
+                    display_name: pkg.display_name.unwrap_or_default(),
+                    id: biosample_id,
+                    extra_json,
+                    extra_columns,
+                });
+            }
+            Err(e) => {
+                if verbose {
+                    println!(
+                        "Failed to parse package for biosample {}: {:?}",
+                        biosample.id, e
+                    );
+                }
+            }
+        }
+    }
+
+    (attr_rows, pkg_rows)
+}
+
+/// Build the configured [`Sink`] and, for DuckDB, report any resume watermark
+/// so the caller can build the matching Mongo filter.
+///
+/// `--extra-mode` and `--resume` are DuckDB-only: PostgresSink/SqliteSink insert
+/// base columns only and never track a watermark, so accepting either flag with
+/// a sqlx backend would silently discard data instead of doing what was asked.
+async fn open_sink(
+    args: &ExportArgs,
+) -> Result<(Box<dyn Sink>, Option<String>), Box<dyn std::error::Error>> {
+    if args.output_backend != OutputBackend::Duckdb {
+        if args.extra_mode.is_some() {
+            return Err(format!(
+                "--extra-mode is only supported with --output-backend duckdb, not {:?}",
+                args.output_backend
+            )
+            .into());
+        }
+        if args.resume {
+            return Err(format!(
+                "--resume is only supported with --output-backend duckdb, not {:?}",
+                args.output_backend
+            )
+            .into());
+        }
+    }
+
+    match args.output_backend {
+        OutputBackend::Duckdb => {
+            let sink = DuckDbSink::open(&args.output_db, args.batch_size, args.extra_mode, args.resume)?;
+            let watermark = sink.resume_watermark()?;
+            Ok((Box::new(sink), watermark))
+        }
+        OutputBackend::Postgres => {
+            let url = args
+                .output_url
+                .as_deref()
+                .ok_or("--output-url is required for --output-backend postgres")?;
+            Ok((Box::new(PostgresSink::connect(url).await?), None))
+        }
+        OutputBackend::Sqlite => {
+            let url = args
+                .output_url
+                .as_deref()
+                .ok_or("--output-url is required for --output-backend sqlite")?;
+            Ok((Box::new(SqliteSink::connect(url).await?), None))
+        }
+    }
+}
+
+/// Extract biosamples from MongoDB into the configured sink (DuckDB, Postgres,
+/// or SQLite). This is today's ingestion pipeline, now reachable as the
+/// `export` subcommand and driven through the [`Sink`] trait.
+pub async fn run_export(
+    mongo_uri: &str,
+    mongo_db: &str,
+    mongo_collection: &str,
+    verbose: bool,
+    args: ExportArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    let (mut sink, watermark) = open_sink(&args).await?;
+    sink.create_schema().await?;
+
+    let mut source = if let Some(input_file) = &args.input_file {
+        if verbose {
+            println!("Replaying biosamples from {}", input_file);
+        }
+        BiosampleSource::File(NdjsonReader::open(input_file)?)
+    } else {
+        if verbose {
+            println!("Connecting to MongoDB at {}", mongo_uri);
+        }
+        let collection = mongo::connect(mongo_uri, mongo_db, mongo_collection).await?;
+        let (filter, sort) = match &watermark {
+            Some(last_id) => {
+                let last_oid = mongodb::bson::oid::ObjectId::parse_str(last_id)?;
+                (doc! { "_id": { "$gt": last_oid } }, doc! { "_id": 1 })
+            }
+            None => (doc! {}, doc! { "_id": 1 }),
+        };
+        let cursor = mongo::stream_biosamples(&collection, filter, Some(sort), args.limit).await?;
+        BiosampleSource::Mongo(Box::new(cursor))
+    };
+
+    let mut export_writer = match &args.export_file {
+        Some(path) => {
+            let format = args.export_format.unwrap_or_else(|| ExportFormat::from_path(path));
+            Some(NdjsonWriter::create(path, format)?)
+        }
+        None => None,
+    };
+
+    let total = if args.limit > 0 {
+        args.limit as u64
+    } else {
+        args.total_biosamples
+    };
+    let pb = progress_bar(total);
+
+    // A replayed file gets no Mongo `$gt` filter, so records at or before the
+    // resume watermark are skipped here instead.
+    let replay_after = match (&args.input_file, &watermark) {
+        (Some(_), Some(last_id)) => Some(mongodb::bson::oid::ObjectId::parse_str(last_id)?),
+        _ => None,
+    };
+
+    let mut processed_count = 0u64;
+    let mut row_count = 0u64;
+    let start = std::time::Instant::now();
+
+    while let Some(biosample) = source.next().await? {
+        if replay_after.map_or(false, |last| biosample.mongo_id <= last) {
+            continue;
+        }
+        processed_count += 1;
+        pb.set_position(processed_count);
+
+        if let Some(writer) = export_writer.as_mut() {
+            writer.write_biosample(&biosample)?;
+        }
+
+        let (attr_rows, pkg_rows) = extract_rows(&biosample, verbose, args.extra_mode);
+        row_count += (attr_rows.len() + pkg_rows.len()) as u64;
+        for row in attr_rows {
+            sink.insert_attribute(row).await?;
+        }
+        for row in pkg_rows {
+            sink.insert_package(row).await?;
+        }
+        sink.end_biosample(&biosample.mongo_id.to_hex()).await?;
+
+        // The Mongo cursor applies `--limit` itself; a replayed file does not.
+        if args.limit > 0 && processed_count >= args.limit as u64 {
+            break;
+        }
+    }
+
+    sink.finish().await?;
+    if let Some(writer) = export_writer {
+        writer.finish()?;
+    }
+
+    pb.finish_with_message("Processing complete");
+    let elapsed = start.elapsed().as_secs_f64();
+    let rows_per_sec = if elapsed > 0.0 {
+        row_count as f64 / elapsed
+    } else {
+        0.0
+    };
+    println!(
+        "Processed {} biosamples ({} rows, {:.1} rows/sec)",
+        processed_count, row_count, rows_per_sec
+    );
+
+    Ok(())
+}
+
+/// Stream the collection without writing anywhere, reporting distinct
+/// `harmonized_name`/`attribute_name` counts and null rates.
+pub async fn run_stats(
+    mongo_uri: &str,
+    mongo_db: &str,
+    mongo_collection: &str,
+    verbose: bool,
+    args: ScanArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if verbose {
+        println!("Connecting to MongoDB at {}", mongo_uri);
+    }
+
+    let collection = mongo::connect(mongo_uri, mongo_db, mongo_collection).await?;
+    let mut cursor = mongo::stream_biosamples(&collection, doc! {}, None, args.limit).await?;
+
+    let total = if args.limit > 0 {
+        args.limit as u64
+    } else {
+        args.total_biosamples
+    };
+    let pb = progress_bar(total);
+
+    let mut processed_count = 0u64;
+    let mut attribute_count = 0u64;
+    let mut attribute_name_null = 0u64;
+    let mut harmonized_name_null = 0u64;
+    let mut distinct_attribute_names = std::collections::HashSet::new();
+    let mut distinct_harmonized_names = std::collections::HashSet::new();
+
+    while let Some(biosample) = cursor.try_next().await? {
+        processed_count += 1;
+        pb.set_position(processed_count);
+
+        if let Some(attrs) = biosample
+            .attributes
+            .as_ref()
+            .and_then(|a| a.get("Attribute"))
+            .and_then(|a| a.as_array())
+        {
+            for attr_value in attrs {
+                if let Ok(attr) = serde_json::from_value::<AttributeData>(attr_value.clone()) {
+                    attribute_count += 1;
+                    match attr.attribute_name {
+                        Some(name) => {
+                            distinct_attribute_names.insert(name);
+                        }
+                        None => attribute_name_null += 1,
+                    }
+                    match attr.harmonized_name {
+                        Some(name) => {
+                            distinct_harmonized_names.insert(name);
+                        }
+                        None => harmonized_name_null += 1,
+                    }
+                }
+            }
+        }
+    }
+
+    pb.finish_with_message("Scan complete");
+    println!("Processed {} biosamples total", processed_count);
+    println!("Attributes seen: {}", attribute_count);
+    println!(
+        "Distinct attribute_name values: {}",
+        distinct_attribute_names.len()
+    );
+    println!(
+        "Distinct harmonized_name values: {}",
+        distinct_harmonized_names.len()
+    );
+    if attribute_count > 0 {
+        println!(
+            "attribute_name null rate: {:.2}%",
+            100.0 * attribute_name_null as f64 / attribute_count as f64
+        );
+        println!(
+            "harmonized_name null rate: {:.2}%",
+            100.0 * harmonized_name_null as f64 / attribute_count as f64
+        );
+    }
+
+    Ok(())
+}
+
+/// Check that every biosample (and its nested attributes/package) parses
+/// cleanly, logging any record whose `extra` flatten bucket is non-empty.
+/// Reads raw BSON documents rather than the typed `Biosample` cursor so a
+/// single malformed record is logged and counted, not a fatal error that
+/// aborts the whole scan.
+pub async fn run_validate(
+    mongo_uri: &str,
+    mongo_db: &str,
+    mongo_collection: &str,
+    verbose: bool,
+    args: ScanArgs,
+) -> Result<(), Box<dyn std::error::Error>> {
+    if verbose {
+        println!("Connecting to MongoDB at {}", mongo_uri);
+    }
+
+    let collection = mongo::connect(mongo_uri, mongo_db, mongo_collection)
+        .await?
+        .clone_with_type::<mongodb::bson::Document>();
+    let mut cursor = mongo::stream_biosamples(&collection, doc! {}, None, args.limit).await?;
+
+    let total = if args.limit > 0 {
+        args.limit as u64
+    } else {
+        args.total_biosamples
+    };
+    let pb = progress_bar(total);
+
+    let mut processed_count = 0u64;
+    let mut unexpected_field_count = 0u64;
+    let mut parse_failure_count = 0u64;
+
+    while let Some(doc) = cursor.try_next().await? {
+        processed_count += 1;
+        pb.set_position(processed_count);
+
+        let biosample = match mongodb::bson::from_document::<Biosample>(doc) {
+            Ok(biosample) => biosample,
+            Err(e) => {
+                parse_failure_count += 1;
+                println!("Biosample #{} failed to parse: {}", processed_count, e);
+                continue;
+            }
+        };
+
+        if !biosample.extra.is_empty() {
+            unexpected_field_count += 1;
+            println!(
+                "Biosample {} has unexpected top-level fields: {:?}",
+                biosample.id, biosample.extra
+            );
+        }
+
+        if let Some(attrs) = biosample
+            .attributes
+            .as_ref()
+            .and_then(|a| a.get("Attribute"))
+            .and_then(|a| a.as_array())
+        {
+            for attr_value in attrs {
+                match serde_json::from_value::<AttributeData>(attr_value.clone()) {
+                    Ok(attr) if !attr.extra.is_empty() => {
+                        unexpected_field_count += 1;
+                        println!(
+                            "Biosample {} has unexpected attribute fields: {:?}",
+                            biosample.id, attr.extra
+                        );
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        parse_failure_count += 1;
+                        println!(
+                            "Biosample {} has an attribute that failed to parse: {}",
+                            biosample.id, e
+                        );
+                    }
+                }
+            }
+        }
+
+        if let Some(package) = &biosample.package {
+            match serde_json::from_value::<PackageData>(package.clone()) {
+                Ok(pkg) if !pkg.extra.is_empty() => {
+                    unexpected_field_count += 1;
+                    println!(
+                        "Biosample {} has unexpected package fields: {:?}",
+                        biosample.id, pkg.extra
+                    );
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    parse_failure_count += 1;
+                    println!(
+                        "Biosample {} has a package that failed to parse: {}",
+                        biosample.id, e
+                    );
+                }
+            }
+        }
+    }
+
+    pb.finish_with_message("Validation complete");
+    println!("Processed {} biosamples total", processed_count);
+    println!(
+        "Records with unexpected extra fields: {}",
+        unexpected_field_count
+    );
+    println!("Records that failed to parse: {}", parse_failure_count);
+
+    Ok(())
+}
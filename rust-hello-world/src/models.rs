@@ -0,0 +1,35 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AttributeData {
+    pub content: Option<String>,
+    pub attribute_name: Option<String>,
+    pub harmonized_name: Option<String>,
+    pub display_name: Option<String>,
+    pub unit: Option<String>,
+    #[serde(flatten)]
+    pub extra: std::collections::BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackageData {
+    pub content: Option<String>,
+    pub display_name: Option<String>,
+    #[serde(flatten)]
+    pub extra: std::collections::BTreeMap<String, Value>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Biosample {
+    pub id: String,
+    /// The MongoDB document `_id`, used as the resumable sync watermark.
+    #[serde(rename = "_id")]
+    pub mongo_id: mongodb::bson::oid::ObjectId,
+    #[serde(rename = "Attributes")]
+    pub attributes: Option<serde_json::Value>,
+    #[serde(rename = "Package")]
+    pub package: Option<serde_json::Value>,
+    #[serde(flatten)]
+    pub extra: std::collections::BTreeMap<String, Value>,
+}
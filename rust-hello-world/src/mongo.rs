@@ -0,0 +1,38 @@
+use duckdb::Result;
+use mongodb::{bson::Document, options::ClientOptions, Client, Collection, Cursor};
+use serde::de::DeserializeOwned;
+
+use crate::models::Biosample;
+
+/// Connect to MongoDB and return the configured biosample collection.
+pub async fn connect(
+    mongo_uri: &str,
+    mongo_db: &str,
+    mongo_collection: &str,
+) -> Result<Collection<Biosample>, Box<dyn std::error::Error>> {
+    let client_options = ClientOptions::parse(mongo_uri).await?;
+    let client = Client::with_options(client_options)?;
+    let db = client.database(mongo_db);
+    Ok(db.collection(mongo_collection))
+}
+
+/// Open a cursor over `collection` honoring `limit` (0 means unbounded), applying
+/// `filter` and `sort` so callers can share the same cursor/stream plumbing. Generic
+/// over the deserialized type so callers that need the raw `Document` (to recover
+/// from per-record parse failures instead of aborting the whole cursor) can reuse it.
+pub async fn stream_biosamples<T>(
+    collection: &Collection<T>,
+    filter: Document,
+    sort: Option<Document>,
+    limit: i64,
+) -> Result<Cursor<T>, Box<dyn std::error::Error>>
+where
+    T: DeserializeOwned + Unpin + Send + Sync,
+{
+    let find_options = mongodb::options::FindOptions::builder()
+        .limit(if limit > 0 { Some(limit) } else { None })
+        .sort(sort)
+        .build();
+
+    Ok(collection.find(filter, find_options).await?)
+}
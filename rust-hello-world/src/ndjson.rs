@@ -0,0 +1,137 @@
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+
+use clap::ValueEnum;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+
+use crate::models::Biosample;
+
+/// Compression applied to the teed NDJSON export/import stream.
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum ExportFormat {
+    #[value(name = "jsonl")]
+    Jsonl,
+    #[value(name = "jsonl.gz")]
+    JsonlGz,
+    #[value(name = "jsonl.zst")]
+    JsonlZst,
+    #[value(name = "jsonl.br")]
+    JsonlBr,
+}
+
+impl ExportFormat {
+    /// Infer the format from a file's extension (`.gz`, `.zst`, `.br`, else plain).
+    pub fn from_path(path: &str) -> Self {
+        if path.ends_with(".gz") {
+            ExportFormat::JsonlGz
+        } else if path.ends_with(".zst") {
+            ExportFormat::JsonlZst
+        } else if path.ends_with(".br") {
+            ExportFormat::JsonlBr
+        } else {
+            ExportFormat::Jsonl
+        }
+    }
+}
+
+enum Encoder {
+    Plain(BufWriter<File>),
+    Gz(GzEncoder<BufWriter<File>>),
+    Zst(zstd::Encoder<'static, BufWriter<File>>),
+    Brotli(Box<brotli::CompressorWriter<BufWriter<File>>>),
+}
+
+impl Write for Encoder {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        match self {
+            Encoder::Plain(w) => w.write(buf),
+            Encoder::Gz(w) => w.write(buf),
+            Encoder::Zst(w) => w.write(buf),
+            Encoder::Brotli(w) => w.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match self {
+            Encoder::Plain(w) => w.flush(),
+            Encoder::Gz(w) => w.flush(),
+            Encoder::Zst(w) => w.flush(),
+            Encoder::Brotli(w) => w.flush(),
+        }
+    }
+}
+
+/// Streams raw `Biosample` documents out to a newline-delimited JSON file,
+/// compressing on the fly so the whole dataset never has to sit in memory.
+pub struct NdjsonWriter {
+    encoder: Encoder,
+}
+
+impl NdjsonWriter {
+    pub fn create(path: &str, format: ExportFormat) -> io::Result<Self> {
+        let file = BufWriter::new(File::create(path)?);
+        let encoder = match format {
+            ExportFormat::Jsonl => Encoder::Plain(file),
+            ExportFormat::JsonlGz => Encoder::Gz(GzEncoder::new(file, Compression::default())),
+            ExportFormat::JsonlZst => Encoder::Zst(zstd::Encoder::new(file, 0)?),
+            ExportFormat::JsonlBr => {
+                Encoder::Brotli(Box::new(brotli::CompressorWriter::new(file, 4096, 9, 22)))
+            }
+        };
+        Ok(Self { encoder })
+    }
+
+    pub fn write_biosample(&mut self, biosample: &Biosample) -> io::Result<()> {
+        serde_json::to_writer(&mut self.encoder, biosample)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        self.encoder.write_all(b"\n")
+    }
+
+    /// Finalize the underlying compressor so trailing frames are flushed to disk.
+    pub fn finish(self) -> io::Result<()> {
+        match self.encoder {
+            Encoder::Plain(mut w) => w.flush(),
+            Encoder::Gz(w) => w.finish().map(|_| ()),
+            Encoder::Zst(w) => w.finish().map(|_| ()),
+            Encoder::Brotli(mut w) => w.flush(),
+        }
+    }
+}
+
+/// Reads back a (possibly compressed) NDJSON file of `Biosample` documents,
+/// so an expensive Mongo scan can be done once and replayed offline.
+pub struct NdjsonReader {
+    lines: Box<dyn Iterator<Item = io::Result<String>>>,
+}
+
+impl NdjsonReader {
+    pub fn open(path: &str) -> io::Result<Self> {
+        let format = ExportFormat::from_path(path);
+        let file = File::open(path)?;
+        let lines: Box<dyn Iterator<Item = io::Result<String>>> = match format {
+            ExportFormat::Jsonl => Box::new(BufReader::new(file).lines()),
+            ExportFormat::JsonlGz => Box::new(BufReader::new(GzDecoder::new(file)).lines()),
+            ExportFormat::JsonlZst => {
+                Box::new(BufReader::new(zstd::Decoder::new(file)?).lines())
+            }
+            ExportFormat::JsonlBr => {
+                Box::new(BufReader::new(brotli::Decompressor::new(file, 4096)).lines())
+            }
+        };
+        Ok(Self { lines })
+    }
+
+    pub fn next_biosample(&mut self) -> io::Result<Option<Biosample>> {
+        match self.lines.next() {
+            Some(line) => {
+                let line = line?;
+                let biosample = serde_json::from_str(&line)
+                    .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+                Ok(Some(biosample))
+            }
+            None => Ok(None),
+        }
+    }
+}
@@ -0,0 +1,530 @@
+use std::collections::BTreeMap;
+
+use async_trait::async_trait;
+use duckdb::{Connection, ToSql};
+use serde_json::Value;
+
+use crate::cli::ExtraMode;
+
+/// A parsed `attribute` row awaiting persistence.
+#[derive(Default, Clone)]
+pub struct AttrRow {
+    pub content: String,
+    pub attribute_name: String,
+    pub id: i64,
+    pub harmonized_name: String,
+    pub display_name: String,
+    pub unit: String,
+    pub extra_json: Option<String>,
+    pub extra_columns: BTreeMap<String, String>,
+}
+
+/// A parsed `package` row, mirroring [`AttrRow`].
+#[derive(Default, Clone)]
+pub struct PkgRow {
+    pub content: String,
+    pub display_name: String,
+    pub id: i64,
+    pub extra_json: Option<String>,
+    pub extra_columns: BTreeMap<String, String>,
+}
+
+/// Destination for extracted biosample rows. The ingestion loop in
+/// `commands::run_export` drives one of these instead of talking to a
+/// concrete database, so the same extractor can feed DuckDB, Postgres, or
+/// SQLite.
+#[async_trait]
+pub trait Sink {
+    /// Create (or migrate) the `attribute`/`package` tables. Must be idempotent.
+    async fn create_schema(&mut self) -> Result<(), Box<dyn std::error::Error>>;
+
+    async fn insert_attribute(&mut self, row: AttrRow) -> Result<(), Box<dyn std::error::Error>>;
+
+    async fn insert_package(&mut self, row: PkgRow) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Called once all of one biosample's rows have been passed to
+    /// `insert_attribute`/`insert_package`. Sinks that buffer rows must only
+    /// flush and advance their resume watermark to `mongo_id` here, never
+    /// mid-biosample, so a crash can never leave a partially-written
+    /// biosample behind the watermark.
+    async fn end_biosample(&mut self, mongo_id: &str) -> Result<(), Box<dyn std::error::Error>>;
+
+    /// Flush any buffered rows and release resources. Called once at the end
+    /// of a run, after the last row has been inserted.
+    async fn finish(&mut self) -> Result<(), Box<dyn std::error::Error>>;
+}
+
+/// Render a JSON scalar as plain text for a VARCHAR `extra` column, falling
+/// back to the JSON encoding for arrays/objects.
+fn extra_value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+/// Shape an `extra` flatten map according to `--extra-mode`: a single JSON
+/// blob, one VARCHAR value per key, or neither when the mode is unset.
+pub fn split_extra(
+    extra: &BTreeMap<String, Value>,
+    extra_mode: Option<ExtraMode>,
+) -> (Option<String>, BTreeMap<String, String>) {
+    match extra_mode {
+        Some(ExtraMode::Json) => (
+            Some(serde_json::to_string(extra).unwrap_or_default()),
+            BTreeMap::new(),
+        ),
+        Some(ExtraMode::Columns) => (
+            None,
+            extra
+                .iter()
+                .map(|(k, v)| (k.clone(), extra_value_to_text(v)))
+                .collect(),
+        ),
+        None => (None, BTreeMap::new()),
+    }
+}
+
+/// The columns every `attribute`/`package` table has regardless of `--extra-mode`,
+/// so a resumed run can tell them apart from previously-added dynamic columns.
+const ATTRIBUTE_BASE_COLUMNS: &[&str] = &[
+    "content",
+    "attribute_name",
+    "id",
+    "harmonized_name",
+    "display_name",
+    "unit",
+];
+const PACKAGE_BASE_COLUMNS: &[&str] = &["content", "display_name", "id"];
+
+/// Tracks the VARCHAR columns already added to a table under
+/// `--extra-mode=columns`, issuing `ALTER TABLE ... ADD COLUMN` only once per key.
+#[derive(Default)]
+struct DynamicColumns {
+    order: Vec<String>,
+}
+
+impl DynamicColumns {
+    /// Whether `key` is a dynamic column tracked under `--extra-mode=columns`:
+    /// neither one of `base_columns` nor the single JSON `extra` column used
+    /// by `--extra-mode=json`. Shared with `reload`
+    /// so a fresh run and a resumed run agree on which keys are dynamic.
+    fn is_dynamic(key: &str, base_columns: &[&str]) -> bool {
+        key != "extra" && !base_columns.contains(&key)
+    }
+
+    fn ensure(&mut self, conn: &Connection, table: &str, key: &str, base_columns: &[&str]) -> duckdb::Result<()> {
+        if !Self::is_dynamic(key, base_columns) || self.order.iter().any(|k| k == key) {
+            return Ok(());
+        }
+        conn.execute(
+            &format!("ALTER TABLE {table} ADD COLUMN IF NOT EXISTS \"{key}\" VARCHAR"),
+            [],
+        )?;
+        self.order.push(key.to_string());
+        Ok(())
+    }
+
+    /// Rebuild `order` from a table that already exists (on `--resume`), in its
+    /// physical column order, so the Appender's positional row shape matches
+    /// the columns the table actually has instead of starting from scratch.
+    fn reload(&mut self, conn: &Connection, table: &str, base_columns: &[&str]) -> duckdb::Result<()> {
+        let mut stmt = conn.prepare(&format!("PRAGMA table_info('{table}')"))?;
+        let mut rows = stmt.query([])?;
+        let mut order = Vec::new();
+        while let Some(row) = rows.next()? {
+            let name: String = row.get(1)?;
+            if Self::is_dynamic(&name, base_columns) {
+                order.push(name);
+            }
+        }
+        self.order = order;
+        Ok(())
+    }
+}
+
+/// DuckDB-backed sink: today's Appender-based bulk load, chunked transactions,
+/// `--extra-mode` schema evolution, and the `_sync_state` resume watermark.
+pub struct DuckDbSink {
+    conn: Connection,
+    batch_size: u64,
+    extra_mode: Option<ExtraMode>,
+    resuming: bool,
+    attr_columns: DynamicColumns,
+    pkg_columns: DynamicColumns,
+    attr_buffer: Vec<AttrRow>,
+    pkg_buffer: Vec<PkgRow>,
+    /// Biosamples (not rows) buffered since the last flush, so a batch always
+    /// ends on a biosample boundary and `last_id` never advances past one
+    /// that's only partially written to `attr_buffer`/`pkg_buffer`.
+    pending_since_flush: u64,
+    last_id: Option<String>,
+}
+
+impl DuckDbSink {
+    pub fn open(
+        output_db: &str,
+        batch_size: u64,
+        extra_mode: Option<ExtraMode>,
+        resume: bool,
+    ) -> Result<Self, Box<dyn std::error::Error>> {
+        let conn = Connection::open(output_db)?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS _sync_state (last_id VARCHAR, updated_at TIMESTAMP)",
+            [],
+        )?;
+
+        let resuming = if resume {
+            conn.query_row("SELECT last_id FROM _sync_state LIMIT 1", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .is_ok()
+        } else {
+            conn.execute("DELETE FROM _sync_state", [])?;
+            false
+        };
+
+        let mut attr_columns = DynamicColumns::default();
+        let mut pkg_columns = DynamicColumns::default();
+        if resuming && extra_mode == Some(ExtraMode::Columns) {
+            attr_columns.reload(&conn, "attribute", ATTRIBUTE_BASE_COLUMNS)?;
+            pkg_columns.reload(&conn, "package", PACKAGE_BASE_COLUMNS)?;
+        }
+
+        Ok(Self {
+            conn,
+            batch_size,
+            extra_mode,
+            resuming,
+            attr_columns,
+            pkg_columns,
+            attr_buffer: Vec::new(),
+            pkg_buffer: Vec::new(),
+            pending_since_flush: 0,
+            last_id: None,
+        })
+    }
+
+    /// The resume watermark stored in `_sync_state`, if any, so the caller can build the
+    /// `{"_id": {"$gt": ...}}` Mongo filter.
+    pub fn resume_watermark(&self) -> Result<Option<String>, Box<dyn std::error::Error>> {
+        if !self.resuming {
+            return Ok(None);
+        }
+        Ok(self
+            .conn
+            .query_row("SELECT last_id FROM _sync_state LIMIT 1", [], |row| {
+                row.get::<_, String>(0)
+            })
+            .ok())
+    }
+
+    fn flush(&mut self) -> duckdb::Result<()> {
+        {
+            let mut appender = self.conn.appender("attribute")?;
+            for row in self.attr_buffer.iter() {
+                let mut values: Vec<Box<dyn ToSql>> = vec![
+                    Box::new(row.content.clone()),
+                    Box::new(row.attribute_name.clone()),
+                    Box::new(row.id),
+                    Box::new(row.harmonized_name.clone()),
+                    Box::new(row.display_name.clone()),
+                    Box::new(row.unit.clone()),
+                ];
+                if self.extra_mode == Some(ExtraMode::Json) {
+                    values.push(Box::new(row.extra_json.clone().unwrap_or_default()));
+                }
+                for column in &self.attr_columns.order {
+                    values.push(Box::new(row.extra_columns.get(column).cloned()));
+                }
+                let refs: Vec<&dyn ToSql> = values.iter().map(|v| v.as_ref()).collect();
+                appender.append_row(refs.as_slice())?;
+            }
+            appender.flush()?;
+        }
+        {
+            let mut appender = self.conn.appender("package")?;
+            for row in self.pkg_buffer.iter() {
+                let mut values: Vec<Box<dyn ToSql>> = vec![
+                    Box::new(row.content.clone()),
+                    Box::new(row.display_name.clone()),
+                    Box::new(row.id),
+                ];
+                if self.extra_mode == Some(ExtraMode::Json) {
+                    values.push(Box::new(row.extra_json.clone().unwrap_or_default()));
+                }
+                for column in &self.pkg_columns.order {
+                    values.push(Box::new(row.extra_columns.get(column).cloned()));
+                }
+                let refs: Vec<&dyn ToSql> = values.iter().map(|v| v.as_ref()).collect();
+                appender.append_row(refs.as_slice())?;
+            }
+            appender.flush()?;
+        }
+
+        if let Some(last_id) = &self.last_id {
+            self.conn.execute("DELETE FROM _sync_state", [])?;
+            self.conn.execute(
+                "INSERT INTO _sync_state (last_id, updated_at) VALUES (?, now())",
+                duckdb::params![last_id],
+            )?;
+        }
+
+        self.conn.execute("COMMIT", [])?;
+        self.conn.execute("BEGIN TRANSACTION", [])?;
+        self.attr_buffer.clear();
+        self.pkg_buffer.clear();
+        self.pending_since_flush = 0;
+        Ok(())
+    }
+
+    fn maybe_flush(&mut self) -> duckdb::Result<()> {
+        if self.pending_since_flush >= self.batch_size {
+            self.flush()?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Sink for DuckDbSink {
+    async fn create_schema(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        if !self.resuming {
+            self.conn.execute("DROP TABLE IF EXISTS attribute", [])?;
+            self.conn.execute("DROP TABLE IF EXISTS package", [])?;
+        }
+
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS attribute (
+                content VARCHAR,
+                attribute_name VARCHAR,
+                id BIGINT,
+                harmonized_name VARCHAR,
+                display_name VARCHAR,
+                unit VARCHAR
+            )",
+            [],
+        )?;
+        self.conn.execute(
+            "CREATE TABLE IF NOT EXISTS package (
+                content VARCHAR,
+                display_name VARCHAR,
+                id BIGINT
+            )",
+            [],
+        )?;
+
+        if self.extra_mode == Some(ExtraMode::Json) {
+            self.conn
+                .execute("ALTER TABLE attribute ADD COLUMN IF NOT EXISTS extra JSON", [])?;
+            self.conn
+                .execute("ALTER TABLE package ADD COLUMN IF NOT EXISTS extra JSON", [])?;
+        }
+
+        self.conn.execute("BEGIN TRANSACTION", [])?;
+        Ok(())
+    }
+
+    async fn insert_attribute(&mut self, row: AttrRow) -> Result<(), Box<dyn std::error::Error>> {
+        if self.extra_mode == Some(ExtraMode::Columns) {
+            for key in row.extra_columns.keys() {
+                self.attr_columns
+                    .ensure(&self.conn, "attribute", key, ATTRIBUTE_BASE_COLUMNS)?;
+            }
+        }
+        self.attr_buffer.push(row);
+        Ok(())
+    }
+
+    async fn insert_package(&mut self, row: PkgRow) -> Result<(), Box<dyn std::error::Error>> {
+        if self.extra_mode == Some(ExtraMode::Columns) {
+            for key in row.extra_columns.keys() {
+                self.pkg_columns
+                    .ensure(&self.conn, "package", key, PACKAGE_BASE_COLUMNS)?;
+            }
+        }
+        self.pkg_buffer.push(row);
+        Ok(())
+    }
+
+    async fn end_biosample(&mut self, mongo_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        self.last_id = Some(mongo_id.to_string());
+        self.pending_since_flush += 1;
+        self.maybe_flush()?;
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        self.flush()?;
+        Ok(())
+    }
+}
+
+/// Postgres-backed sink, selected via `--output-backend postgres --output-url ...`.
+pub struct PostgresSink {
+    pool: sqlx::PgPool,
+}
+
+impl PostgresSink {
+    pub async fn connect(url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            pool: sqlx::PgPool::connect(url).await?,
+        })
+    }
+}
+
+#[async_trait]
+impl Sink for PostgresSink {
+    async fn create_schema(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Postgres/Sqlite never support `--resume` (rejected in `open_sink`), so every
+        // run here is a fresh run: drop first to match the DuckDB no-resume behavior
+        // instead of silently appending a duplicate set of rows.
+        sqlx::query("DROP TABLE IF EXISTS attribute")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DROP TABLE IF EXISTS package")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS attribute (
+                content VARCHAR,
+                attribute_name VARCHAR,
+                id BIGINT,
+                harmonized_name VARCHAR,
+                display_name VARCHAR,
+                unit VARCHAR
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS package (
+                content VARCHAR,
+                display_name VARCHAR,
+                id BIGINT
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_attribute(&mut self, row: AttrRow) -> Result<(), Box<dyn std::error::Error>> {
+        sqlx::query(
+            "INSERT INTO attribute (content, attribute_name, id, harmonized_name, display_name, unit)
+             VALUES ($1, $2, $3, $4, $5, $6)",
+        )
+        .bind(row.content)
+        .bind(row.attribute_name)
+        .bind(row.id)
+        .bind(row.harmonized_name)
+        .bind(row.display_name)
+        .bind(row.unit)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_package(&mut self, row: PkgRow) -> Result<(), Box<dyn std::error::Error>> {
+        sqlx::query("INSERT INTO package (content, display_name, id) VALUES ($1, $2, $3)")
+            .bind(row.content)
+            .bind(row.display_name)
+            .bind(row.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn end_biosample(&mut self, _mongo_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}
+
+/// SQLite-backed sink, selected via `--output-backend sqlite --output-url ...`.
+pub struct SqliteSink {
+    pool: sqlx::SqlitePool,
+}
+
+impl SqliteSink {
+    pub async fn connect(url: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        Ok(Self {
+            pool: sqlx::SqlitePool::connect(url).await?,
+        })
+    }
+}
+
+#[async_trait]
+impl Sink for SqliteSink {
+    async fn create_schema(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        // Postgres/Sqlite never support `--resume` (rejected in `open_sink`), so every
+        // run here is a fresh run: drop first to match the DuckDB no-resume behavior
+        // instead of silently appending a duplicate set of rows.
+        sqlx::query("DROP TABLE IF EXISTS attribute")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query("DROP TABLE IF EXISTS package")
+            .execute(&self.pool)
+            .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS attribute (
+                content TEXT,
+                attribute_name TEXT,
+                id INTEGER,
+                harmonized_name TEXT,
+                display_name TEXT,
+                unit TEXT
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+        sqlx::query(
+            "CREATE TABLE IF NOT EXISTS package (
+                content TEXT,
+                display_name TEXT,
+                id INTEGER
+            )",
+        )
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_attribute(&mut self, row: AttrRow) -> Result<(), Box<dyn std::error::Error>> {
+        sqlx::query(
+            "INSERT INTO attribute (content, attribute_name, id, harmonized_name, display_name, unit)
+             VALUES (?, ?, ?, ?, ?, ?)",
+        )
+        .bind(row.content)
+        .bind(row.attribute_name)
+        .bind(row.id)
+        .bind(row.harmonized_name)
+        .bind(row.display_name)
+        .bind(row.unit)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    async fn insert_package(&mut self, row: PkgRow) -> Result<(), Box<dyn std::error::Error>> {
+        sqlx::query("INSERT INTO package (content, display_name, id) VALUES (?, ?, ?)")
+            .bind(row.content)
+            .bind(row.display_name)
+            .bind(row.id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+
+    async fn end_biosample(&mut self, _mongo_id: &str) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+
+    async fn finish(&mut self) -> Result<(), Box<dyn std::error::Error>> {
+        Ok(())
+    }
+}